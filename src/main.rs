@@ -1,133 +1,743 @@
+mod predicate;
+mod schema;
+
 use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::process;
+use std::time::{Duration, Instant};
+
+use predicate::Predicate;
+use schema::Schema;
+
+/// Default fraction of rows allowed to be skipped before `main` exits with
+/// code 2, so a pipeline stage can fail loudly on garbage input.
+const DEFAULT_MAX_BAD_RATIO: f64 = 0.10;
+
+/// Wraps a reader to count the bytes pulled through it, since a generic
+/// `impl BufRead` (stdin or a file) has no `metadata()` to ask for a byte
+/// count up front.
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: u64,
+}
 
-/// Default file paths matching the original COBOL program.
-const DEFAULT_INPUT: &str = "/nfs_dir/input/info.csv";
-const DEFAULT_OUTPUT: &str = "/nfs_dir/output/output.txt";
-
-/// Field widths and filler sizes matching the COBOL record layout:
-///   OUT-LAST-NAME  PIC X(25)  + FILLER PIC X(5)
-///   OUT-FIRST-NAME PIC X(15)  + FILLER PIC X(5)
-///   OUT-STREET     PIC X(30)  + FILLER PIC X(5)
-///   OUT-CITY       PIC X(15)  + FILLER PIC X(5)
-///   OUT-STATE      PIC XXX    + FILLER PIC X(5)
-///   OUT-ZIP        PIC X(10)  + FILLER PIC X(38)
-const FIELD_WIDTHS: [(usize, usize); 6] = [
-    (25, 5),  // last name + filler
-    (15, 5),  // first name + filler
-    (30, 5),  // street + filler
-    (15, 5),  // city + filler
-    (3, 5),   // state + filler
-    (10, 38), // zip + trailing filler
-];
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read += n as u64;
+        Ok(n)
+    }
+}
 
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.bytes_read += amt as u64;
+        self.inner.consume(amt)
+    }
+}
+
+/// `BufReader`/`BufWriter` capacity used for file and stdio I/O. Larger than
+/// the 8 KiB default, which measurably beats it on bulk mainframe exports.
+const IO_BUFFER_CAPACITY: usize = 256 * 1024;
+
+/// A CSV record whose column values line up, in order, with a `Schema`.
+/// Its `values` are reused across records via `fill_from` rather than being
+/// reallocated per row.
 struct AddressRecord {
-    last_name: String,
-    first_name: String,
-    street: String,
-    city: String,
-    state: String,
-    zip: String,
-}
-
-/// Pad or truncate a string to exactly `width` characters, right-padded with spaces.
-fn pad_right(s: &str, width: usize) -> String {
-    if s.len() >= width {
-        s[..width].to_string()
+    values: Vec<String>,
+}
+
+impl AddressRecord {
+    fn with_capacity(columns: usize) -> AddressRecord {
+        AddressRecord {
+            values: vec![String::new(); columns],
+        }
+    }
+
+    /// Overwrite `self.values` with `fields`, reusing each `String`'s
+    /// existing allocation via `clear` + `push_str` instead of allocating a
+    /// fresh `String` per field.
+    fn fill_from(&mut self, fields: &[String]) {
+        for (value, field) in self.values.iter_mut().zip(fields.iter()) {
+            value.clear();
+            value.push_str(field);
+        }
+    }
+}
+
+/// How much whitespace trimming is applied to a record after it is split
+/// into fields, before it is handed to `parse_csv_line`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Trim {
+    /// Keep every byte verbatim; needed when exact-width passthrough matters.
+    None,
+    /// Trim leading/trailing ASCII whitespace from each field.
+    Fields,
+    /// Same as `Fields`, applied uniformly across the whole record (there is
+    /// no separate header row in this format, so this is the "trim
+    /// everything" mode).
+    All,
+}
+
+impl Trim {
+    fn parse(s: &str) -> Result<Trim, String> {
+        match s {
+            "none" => Ok(Trim::None),
+            "fields" => Ok(Trim::Fields),
+            "headers" | "all" => Ok(Trim::All),
+            other => Err(format!(
+                "invalid --trim value {:?} (expected none, fields, or all)",
+                other
+            )),
+        }
+    }
+}
+
+/// Trim leading/trailing ASCII whitespace from each field of `fields` in
+/// place, mirroring a record-level trim pass rather than trimming fields
+/// individually as they are produced.
+fn trim_record(fields: &mut [String], mode: Trim) {
+    if mode == Trim::None {
+        return;
+    }
+    for field in fields.iter_mut() {
+        let end = field
+            .trim_end_matches(|c: char| c.is_ascii_whitespace())
+            .len();
+        field.truncate(end);
+        let start = field.len()
+            - field
+                .trim_start_matches(|c: char| c.is_ascii_whitespace())
+                .len();
+        field.drain(..start);
+    }
+}
+
+/// Append `s`, padded or truncated to exactly `width` bytes, to `line`.
+/// Truncation never splits a multi-byte UTF-8 character: if `width` falls
+/// inside one, the cut backs up to the preceding character boundary and the
+/// shortfall is made up with extra padding, so the appended chunk is always
+/// exactly `width` bytes.
+fn push_padded(line: &mut String, s: &str, width: usize) {
+    let truncated = if s.len() <= width {
+        s
     } else {
-        format!("{:<width$}", s, width = width)
+        let mut end = width;
+        while end > 0 && !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        &s[..end]
+    };
+    line.push_str(truncated);
+    for _ in 0..(width - truncated.len()) {
+        line.push(' ');
     }
 }
 
-/// Format an address record as a fixed-width line matching the COBOL output layout.
-fn format_fixed_width(record: &AddressRecord) -> String {
-    let fields = [
-        &record.last_name,
-        &record.first_name,
-        &record.street,
-        &record.city,
-        &record.state,
-        &record.zip,
-    ];
+/// Format a record as a fixed-width line, driven entirely by `schema`,
+/// writing into the caller's reused `line` buffer instead of returning a
+/// freshly allocated `String`.
+fn format_fixed_width(record: &AddressRecord, schema: &Schema, line: &mut String) {
+    line.clear();
+    for (value, field) in record.values.iter().zip(schema.fields.iter()) {
+        push_padded(line, value, field.width);
+        push_padded(line, "", field.filler);
+    }
+}
 
-    let mut line = String::with_capacity(161);
-    for (field, &(field_width, filler_width)) in fields.iter().zip(FIELD_WIDTHS.iter()) {
-        line.push_str(&pad_right(field, field_width));
-        line.push_str(&pad_right("", filler_width));
+/// States of the RFC 4180 field scanner used by `read_csv_record`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CsvState {
+    StartField,
+    InUnquoted,
+    InQuoted,
+    InQuoteMaybeEnd,
+}
+
+/// A reusable pool of field buffers for one CSV record. Each field is
+/// scanned into a raw byte buffer (a mainframe export is not guaranteed to
+/// be valid UTF-8), then decoded into a `String` once the record is
+/// complete via `finish_record`. Both the raw and decoded buffers are
+/// cleared and refilled across records instead of being reallocated, so a
+/// bulk conversion run does one allocation per distinct field position
+/// rather than one per field per row.
+struct FieldBuffer {
+    raw: Vec<Vec<u8>>,
+    decoded: Vec<String>,
+    /// Index of the field currently being built; completed fields are
+    /// `raw[..=cursor]` / `decoded[..=cursor]`.
+    cursor: usize,
+}
+
+impl FieldBuffer {
+    fn new() -> FieldBuffer {
+        FieldBuffer {
+            raw: vec![Vec::new()],
+            decoded: vec![String::new()],
+            cursor: 0,
+        }
+    }
+
+    /// Reset for a new record, reusing the existing buffer allocations.
+    fn begin_record(&mut self) {
+        self.cursor = 0;
+        self.raw[0].clear();
+    }
+
+    /// Append a raw input byte to the current field.
+    fn push_byte(&mut self, byte: u8) {
+        self.raw[self.cursor].push(byte);
+    }
+
+    /// Finish the current field and start the next one.
+    fn next_field(&mut self) {
+        self.cursor += 1;
+        if self.cursor == self.raw.len() {
+            self.raw.push(Vec::new());
+            self.decoded.push(String::new());
+        } else {
+            self.raw[self.cursor].clear();
+        }
+    }
+
+    /// Decode every field scanned so far from raw bytes into `decoded`. A
+    /// mainframe export can contain stray non-UTF-8 bytes; rather than
+    /// assume the input is clean (or reinterpret each byte as a `char`, which
+    /// would corrupt every multi-byte character),
+    /// invalid sequences are replaced with U+FFFD, matching what
+    /// `String::from_utf8_lossy` would produce.
+    fn finish_record(&mut self) {
+        for i in 0..=self.cursor {
+            self.decoded[i].clear();
+            match std::str::from_utf8(&self.raw[i]) {
+                Ok(valid) => self.decoded[i].push_str(valid),
+                Err(_) => self.decoded[i].push_str(&String::from_utf8_lossy(&self.raw[i])),
+            }
+        }
+    }
+
+    fn fields(&self) -> &[String] {
+        &self.decoded[..=self.cursor]
+    }
+
+    fn fields_mut(&mut self) -> &mut [String] {
+        &mut self.decoded[..=self.cursor]
     }
-    line
 }
 
-/// Parse a CSV line into an AddressRecord by splitting on commas.
-/// Mirrors the COBOL UNSTRING ... DELIMITED BY "," logic.
-fn parse_csv_line(line: &str) -> Option<AddressRecord> {
-    let fields: Vec<&str> = line.split(',').collect();
-    if fields.len() != 6 {
-        return None;
+/// Read one CSV record from `reader` into `buf`, honoring RFC 4180 quoting:
+/// a leading `"` opens a quoted field, `""` inside a quoted field is a
+/// literal quote, and delimiters/newlines inside quotes are ordinary data
+/// rather than delimiters. A quoted field may therefore span multiple
+/// physical lines. `delimiter` is the field separator byte (a comma by
+/// default, but configurable on the command line).
+///
+/// Scans directly out of `reader`'s own fill/consume buffer rather than
+/// issuing a `Read::read` call per byte, since `reader` is already
+/// buffered at `IO_BUFFER_CAPACITY`.
+///
+/// Returns `Ok(false)` once there is no more input to read; otherwise
+/// `buf.fields()` holds the scanned record, already decoded from raw bytes
+/// (invalid UTF-8 replaced with U+FFFD rather than panicking).
+fn read_csv_record<R: BufRead>(
+    reader: &mut R,
+    delimiter: u8,
+    buf: &mut FieldBuffer,
+) -> io::Result<bool> {
+    buf.begin_record();
+    let mut state = CsvState::StartField;
+    let mut saw_any_byte = false;
+
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            break;
+        }
+        saw_any_byte = true;
+
+        let mut consumed = 0;
+        for &raw in available {
+            consumed += 1;
+            let is_delim = raw == delimiter;
+
+            match state {
+                CsvState::StartField => {
+                    if raw == b'"' {
+                        state = CsvState::InQuoted;
+                    } else if is_delim {
+                        buf.next_field();
+                    } else if raw == b'\r' {
+                    } else if raw == b'\n' {
+                        reader.consume(consumed);
+                        buf.finish_record();
+                        return Ok(true);
+                    } else {
+                        buf.push_byte(raw);
+                        state = CsvState::InUnquoted;
+                    }
+                }
+                CsvState::InUnquoted => {
+                    if is_delim {
+                        buf.next_field();
+                        state = CsvState::StartField;
+                    } else if raw == b'\r' {
+                    } else if raw == b'\n' {
+                        reader.consume(consumed);
+                        buf.finish_record();
+                        return Ok(true);
+                    } else {
+                        buf.push_byte(raw);
+                    }
+                }
+                CsvState::InQuoted => {
+                    if raw == b'"' {
+                        state = CsvState::InQuoteMaybeEnd;
+                    } else {
+                        buf.push_byte(raw);
+                    }
+                }
+                CsvState::InQuoteMaybeEnd => {
+                    if raw == b'"' {
+                        buf.push_byte(b'"');
+                        state = CsvState::InQuoted;
+                    } else if is_delim {
+                        buf.next_field();
+                        state = CsvState::StartField;
+                    } else if raw == b'\r' {
+                        state = CsvState::InUnquoted;
+                    } else if raw == b'\n' {
+                        reader.consume(consumed);
+                        buf.finish_record();
+                        return Ok(true);
+                    } else {
+                        buf.push_byte(raw);
+                        state = CsvState::InUnquoted;
+                    }
+                }
+            }
+        }
+        reader.consume(consumed);
+    }
+
+    if saw_any_byte {
+        buf.finish_record();
+    }
+    Ok(saw_any_byte)
+}
+
+/// Fill `record` from already-scanned CSV fields, validating the column
+/// count against `schema` rather than a hardcoded magic number. Mirrors the
+/// COBOL UNSTRING ... DELIMITED BY "," logic, but the splitting itself now
+/// happens in `read_csv_record`, and trimming (if any) has already been
+/// applied by `trim_record`.
+fn parse_csv_line(fields: &[String], schema: &Schema, record: &mut AddressRecord) -> bool {
+    if fields.len() != schema.column_count() {
+        return false;
     }
-    Some(AddressRecord {
-        last_name: fields[0].trim().to_string(),
-        first_name: fields[1].trim().to_string(),
-        street: fields[2].trim().to_string(),
-        city: fields[3].trim().to_string(),
-        state: fields[4].trim().to_string(),
-        zip: fields[5].trim().to_string(),
-    })
+    record.fill_from(fields);
+    true
 }
 
-/// Read CSV input, convert each record to fixed-width format, and write the output.
-fn process_csv(input_path: &str, output_path: &str) -> io::Result<()> {
-    let input_file = File::open(input_path)?;
-    let reader = BufReader::new(input_file);
+/// Summary of a `process_csv` run, reported to the caller so it can decide
+/// whether the bad-row rate warrants a non-zero exit code.
+struct ProcessStats {
+    processed: u64,
+    filtered: u64,
+    skipped: u64,
+    bytes: u64,
+    elapsed: Duration,
+}
 
-    let output_file = File::create(output_path)?;
-    let mut writer = BufWriter::new(output_file);
+/// Read CSV input, convert each record to fixed-width format per `schema`,
+/// and write the output. Rows with the wrong field count are counted as
+/// skipped rather than aborting the run; rows that fail a `--search`/
+/// `--where` predicate are dropped silently and counted as filtered.
+fn process_csv(
+    input: impl BufRead,
+    output: impl Write,
+    schema: &Schema,
+    trim: Trim,
+    delimiter: u8,
+    predicates: &[Predicate],
+) -> io::Result<ProcessStats> {
+    let start = Instant::now();
+
+    let mut reader = CountingReader {
+        inner: input,
+        bytes_read: 0,
+    };
+    let mut writer = output;
 
     let mut record_count = 0u64;
+    let mut filtered_count = 0u64;
+    let mut skipped_count = 0u64;
+    let mut record_num = 0u64;
 
-    for (line_num, line_result) in reader.lines().enumerate() {
-        let line = line_result?;
-        if line.trim().is_empty() {
+    let mut field_buf = FieldBuffer::new();
+    let mut record = AddressRecord::with_capacity(schema.column_count());
+    let mut line = String::with_capacity(161);
+
+    while read_csv_record(&mut reader, delimiter, &mut field_buf)? {
+        record_num += 1;
+        let fields = field_buf.fields_mut();
+        if fields.len() == 1 && fields[0].trim().is_empty() {
             continue;
         }
 
-        match parse_csv_line(&line) {
-            Some(record) => {
-                writeln!(writer, "{}", format_fixed_width(&record))?;
+        trim_record(fields, trim);
+
+        if parse_csv_line(field_buf.fields(), schema, &mut record) {
+            if predicates.iter().all(|p| p.matches(&record.values)) {
+                format_fixed_width(&record, schema, &mut line);
+                writeln!(writer, "{}", line)?;
                 record_count += 1;
+            } else {
+                filtered_count += 1;
             }
-            None => {
-                eprintln!(
-                    "Warning: line {} has unexpected number of fields, skipping",
-                    line_num + 1
-                );
-            }
+        } else {
+            skipped_count += 1;
+            eprintln!(
+                "Warning: record {} has unexpected number of fields (expected {}, got {}), skipping",
+                record_num,
+                schema.column_count(),
+                field_buf.fields().len()
+            );
         }
     }
 
     writer.flush()?;
-    eprintln!("Successfully processed {} records", record_count);
-    Ok(())
+    let stats = ProcessStats {
+        processed: record_count,
+        filtered: filtered_count,
+        skipped: skipped_count,
+        bytes: reader.bytes_read,
+        elapsed: start.elapsed(),
+    };
+    eprintln!(
+        "Summary: processed={} filtered={} skipped={} bytes={} elapsed={:.3}s",
+        stats.processed,
+        stats.filtered,
+        stats.skipped,
+        stats.bytes,
+        stats.elapsed.as_secs_f64()
+    );
+    Ok(stats)
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    let schema_path = expect_flag_value(extract_flag_value(&mut args, "--schema"));
+    let schema = match schema_path {
+        Some(path) => match Schema::load(&path) {
+            Ok(schema) => schema,
+            Err(e) => {
+                eprintln!("Error: failed to load schema from {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => Schema::default_copybook(),
+    };
+
+    let trim = match expect_flag_value(extract_flag_value(&mut args, "--trim")) {
+        Some(value) => match Trim::parse(&value) {
+            Ok(trim) => trim,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        },
+        None => Trim::Fields,
+    };
+
+    let delimiter = match expect_flag_value(extract_flag_value(&mut args, "--delimiter")) {
+        Some(value) => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii() => c as u8,
+                _ => {
+                    eprintln!("Error: --delimiter must be a single ASCII character");
+                    process::exit(1);
+                }
+            }
+        }
+        None => b',',
+    };
+
+    let max_bad_ratio = match expect_flag_value(extract_flag_value(&mut args, "--max-bad-ratio")) {
+        Some(value) => match value.parse::<f64>() {
+            Ok(ratio) => ratio,
+            Err(_) => {
+                eprintln!("Error: --max-bad-ratio must be a number, got {:?}", value);
+                process::exit(1);
+            }
+        },
+        None => DEFAULT_MAX_BAD_RATIO,
+    };
+
+    let mut predicates = Vec::new();
+    if let Some(spec) = expect_flag_value(extract_flag_value(&mut args, "--search")) {
+        match Predicate::parse_search(&spec, &schema) {
+            Ok(predicate) => predicates.push(predicate),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    if let Some((column, op, value)) = expect_where(extract_where(&mut args)) {
+        match Predicate::parse_where(&column, &op, &value, &schema) {
+            Ok(predicate) => predicates.push(predicate),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
 
-    let (input_path, output_path) = if args.len() > 2 {
-        (args[1].as_str(), args[2].as_str())
+    // With no positional arguments this acts as a Unix filter stage, reading
+    // from stdin and writing to stdout instead of fixed NFS paths.
+    let (input_path, output_path) = if args.len() > 1 {
+        (Some(args[0].clone()), Some(args[1].clone()))
     } else {
-        (DEFAULT_INPUT, DEFAULT_OUTPUT)
+        (None, None)
+    };
+
+    eprintln!(
+        "Reading from: {}",
+        input_path.as_deref().unwrap_or("<stdin>")
+    );
+    eprintln!(
+        "Writing to: {}",
+        output_path.as_deref().unwrap_or("<stdout>")
+    );
+
+    let result = match (&input_path, &output_path) {
+        (Some(input_path), Some(output_path)) => File::open(input_path).and_then(|input_file| {
+            File::create(output_path).and_then(|output_file| {
+                process_csv(
+                    BufReader::with_capacity(IO_BUFFER_CAPACITY, input_file),
+                    BufWriter::with_capacity(IO_BUFFER_CAPACITY, output_file),
+                    &schema,
+                    trim,
+                    delimiter,
+                    &predicates,
+                )
+            })
+        }),
+        _ => process_csv(
+            BufReader::with_capacity(IO_BUFFER_CAPACITY, io::stdin().lock()),
+            BufWriter::with_capacity(IO_BUFFER_CAPACITY, io::stdout().lock()),
+            &schema,
+            trim,
+            delimiter,
+            &predicates,
+        ),
     };
 
-    eprintln!("Reading from: {}", input_path);
-    eprintln!("Writing to: {}", output_path);
+    let stats = match result {
+        Ok(stats) => stats,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
 
-    if let Err(e) = process_csv(input_path, output_path) {
-        eprintln!("Error: {}", e);
-        process::exit(1);
+    let total = stats.processed + stats.filtered + stats.skipped;
+    let bad_ratio = if total == 0 {
+        0.0
+    } else {
+        stats.skipped as f64 / total as f64
+    };
+    if bad_ratio > max_bad_ratio {
+        eprintln!(
+            "Error: bad-row ratio {:.1}% exceeds threshold {:.1}%",
+            bad_ratio * 100.0,
+            max_bad_ratio * 100.0
+        );
+        process::exit(2);
     }
 
     eprintln!("Processing complete");
 }
+
+/// Pull `--flag value` out of `args` in place. Returns `Ok(None)` if the
+/// flag isn't present, `Ok(Some(value))` if it is and has a value, or
+/// `Err` if the flag is present but has no value following it (the flag
+/// itself is still removed in that case, so it can't be mistaken for a
+/// positional argument).
+fn extract_flag_value(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, String> {
+    let idx = match args.iter().position(|a| a == flag) {
+        Some(idx) => idx,
+        None => return Ok(None),
+    };
+    if idx + 1 >= args.len() {
+        args.remove(idx);
+        return Err(format!("missing value for {}", flag));
+    }
+    args.remove(idx); // remove the flag
+    Ok(Some(args.remove(idx))) // remove and return the value that followed it
+}
+
+/// Exit with a usage error if `extract_flag_value` reported a missing value.
+fn expect_flag_value(result: Result<Option<String>, String>) -> Option<String> {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Pull `--where <column> <op> <value>` out of `args` in place. Returns
+/// `Ok(None)` if `--where` isn't present, or `Err` (after still removing
+/// the flag) if fewer than 3 arguments follow it.
+fn extract_where(args: &mut Vec<String>) -> Result<Option<(String, String, String)>, String> {
+    let idx = match args.iter().position(|a| a == "--where") {
+        Some(idx) => idx,
+        None => return Ok(None),
+    };
+    if idx + 3 >= args.len() {
+        args.remove(idx);
+        return Err("--where requires 3 arguments: <column> <op> <value>".to_string());
+    }
+    args.remove(idx); // remove the flag
+    let column = args.remove(idx);
+    let op = args.remove(idx);
+    let value = args.remove(idx);
+    Ok(Some((column, op, value)))
+}
+
+/// Exit with a usage error if `extract_where` reported a malformed predicate.
+fn expect_where(result: Result<Option<(String, String, String)>, String>) -> Option<(String, String, String)> {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Scan every record out of `input` with a comma delimiter, returning
+    /// each as an owned `Vec<String>` for easy comparison in assertions.
+    fn scan_all(input: &[u8]) -> Vec<Vec<String>> {
+        let mut reader = Cursor::new(input);
+        let mut buf = FieldBuffer::new();
+        let mut records = Vec::new();
+        while read_csv_record(&mut reader, b',', &mut buf).unwrap() {
+            records.push(buf.fields().to_vec());
+        }
+        records
+    }
+
+    #[test]
+    fn splits_simple_unquoted_fields() {
+        let records = scan_all(b"a,b,c\n");
+        assert_eq!(records, vec![vec!["a", "b", "c"]]);
+    }
+
+    #[test]
+    fn keeps_comma_inside_quoted_field() {
+        let records = scan_all(b"\"Smith, Jr.\",John\n");
+        assert_eq!(records, vec![vec!["Smith, Jr.", "John"]]);
+    }
+
+    #[test]
+    fn unescapes_doubled_quotes_in_quoted_field() {
+        let records = scan_all(b"\"She said \"\"hi\"\"\",John\n");
+        assert_eq!(records, vec![vec!["She said \"hi\"", "John"]]);
+    }
+
+    #[test]
+    fn quoted_field_spans_multiple_physical_lines() {
+        let records = scan_all(b"Doe,\"1 Elm\nSt\",Metropolis\n");
+        assert_eq!(records, vec![vec!["Doe", "1 Elm\nSt", "Metropolis"]]);
+    }
+
+    #[test]
+    fn handles_multiple_records() {
+        let records = scan_all(b"a,b\nc,d\n");
+        assert_eq!(records, vec![vec!["a", "b"], vec!["c", "d"]]);
+    }
+
+    #[test]
+    fn handles_trailing_record_without_newline() {
+        let records = scan_all(b"a,b,c");
+        assert_eq!(records, vec![vec!["a", "b", "c"]]);
+    }
+
+    #[test]
+    fn empty_input_yields_no_records() {
+        let records = scan_all(b"");
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn passes_through_non_ascii_bytes_unchanged() {
+        // "Müller" contains a 2-byte UTF-8 character (0xC3 0xBC); a naive
+        // `byte as char` reinterpretation would corrupt it.
+        let records = scan_all("Müller,John\n".as_bytes());
+        assert_eq!(records, vec![vec!["Müller", "John"]]);
+    }
+
+    #[test]
+    fn replaces_invalid_utf8_instead_of_panicking() {
+        // 0xFF 0xFE is not valid UTF-8 in any position; mainframe exports
+        // routinely contain stray bytes like this and must not crash the
+        // scanner. Each invalid byte becomes its own U+FFFD replacement
+        // character, matching `String::from_utf8_lossy`.
+        let mut input = b"Doe,John,".to_vec();
+        input.extend_from_slice(b"\xff\xfeStreet");
+        input.extend_from_slice(b",City,CA,90210\n");
+        let records = scan_all(&input);
+        assert_eq!(
+            records,
+            vec![vec!["Doe", "John", "\u{fffd}\u{fffd}Street", "City", "CA", "90210"]]
+        );
+    }
+
+    #[test]
+    fn respects_custom_delimiter() {
+        let mut reader = Cursor::new(b"a;b;c\n".as_slice());
+        let mut buf = FieldBuffer::new();
+        assert!(read_csv_record(&mut reader, b';', &mut buf).unwrap());
+        assert_eq!(buf.fields(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn push_padded_truncates_at_char_boundary_not_mid_character() {
+        // Width 1 falls in the middle of the 2-byte 'ü'; the cut must back
+        // up to the preceding boundary instead of panicking or splitting it.
+        let mut line = String::new();
+        push_padded(&mut line, "üb", 1);
+        assert_eq!(line, " ");
+    }
+
+    #[test]
+    fn push_padded_pads_short_strings() {
+        let mut line = String::new();
+        push_padded(&mut line, "ab", 5);
+        assert_eq!(line, "ab   ");
+    }
+
+    #[test]
+    fn trim_parses_known_modes() {
+        assert_eq!(Trim::parse("none").unwrap(), Trim::None);
+        assert_eq!(Trim::parse("fields").unwrap(), Trim::Fields);
+        assert_eq!(Trim::parse("all").unwrap(), Trim::All);
+        assert_eq!(Trim::parse("headers").unwrap(), Trim::All);
+        assert!(Trim::parse("bogus").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::schema::Schema;
+
+/// Comparison to apply between a record's column and a predicate value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Op {
+    Eq,
+    Ne,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn parse(s: &str) -> Option<Op> {
+        match s {
+            "==" => Some(Op::Eq),
+            "!=" => Some(Op::Ne),
+            "contains" => Some(Op::Contains),
+            "starts_with" | "begins_with" => Some(Op::StartsWith),
+            "ends_with" => Some(Op::EndsWith),
+            "<" => Some(Op::Lt),
+            "<=" => Some(Op::Le),
+            ">" => Some(Op::Gt),
+            ">=" => Some(Op::Ge),
+            _ => None,
+        }
+    }
+}
+
+/// A `--search`/`--where` filter evaluated against a parsed record before it
+/// is formatted, so a row can be dropped from the pipeline without ever
+/// reaching `format_fixed_width`.
+pub struct Predicate {
+    column_index: usize,
+    op: Op,
+    value: String,
+}
+
+impl Predicate {
+    /// Parse `--search <column>=<substring>`, a shorthand for a `contains`
+    /// predicate on that column.
+    pub fn parse_search(spec: &str, schema: &Schema) -> Result<Predicate, String> {
+        let (column, substring) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("invalid --search {:?} (expected column=substring)", spec))?;
+        let column_index = schema
+            .index_of(column)
+            .ok_or_else(|| format!("--search: unknown column {:?}", column))?;
+        Ok(Predicate {
+            column_index,
+            op: Op::Contains,
+            value: substring.to_string(),
+        })
+    }
+
+    /// Parse `--where <column> <op> <value>`.
+    pub fn parse_where(column: &str, op: &str, value: &str, schema: &Schema) -> Result<Predicate, String> {
+        let column_index = schema
+            .index_of(column)
+            .ok_or_else(|| format!("--where: unknown column {:?}", column))?;
+        let op = Op::parse(op).ok_or_else(|| {
+            format!(
+                "--where: unknown operator {:?} (expected ==, !=, <, <=, >, >=, contains, starts_with, ends_with)",
+                op
+            )
+        })?;
+        Ok(Predicate {
+            column_index,
+            op,
+            value: value.to_string(),
+        })
+    }
+
+    /// Evaluate this predicate against a record's already-scanned values.
+    pub fn matches(&self, values: &[String]) -> bool {
+        let field = values[self.column_index].as_str();
+        match self.op {
+            Op::Eq => field == self.value,
+            Op::Ne => field != self.value,
+            Op::Contains => field.contains(&self.value),
+            Op::StartsWith => field.starts_with(&self.value),
+            Op::EndsWith => field.ends_with(&self.value),
+            Op::Lt => field < self.value.as_str(),
+            Op::Le => field <= self.value.as_str(),
+            Op::Gt => field > self.value.as_str(),
+            Op::Ge => field >= self.value.as_str(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::parse("last_name,25,5\nstate,3,5\nzip,10,38\n").unwrap()
+    }
+
+    #[test]
+    fn parse_search_builds_contains_predicate() {
+        let schema = schema();
+        let predicate = Predicate::parse_search("state=C", &schema).unwrap();
+        assert!(predicate.matches(&["Doe".to_string(), "NC".to_string(), "27601".to_string()]));
+        assert!(!predicate.matches(&["Doe".to_string(), "VA".to_string(), "22102".to_string()]));
+    }
+
+    #[test]
+    fn parse_search_rejects_missing_equals() {
+        assert!(Predicate::parse_search("state", &schema()).is_err());
+    }
+
+    #[test]
+    fn parse_search_rejects_unknown_column() {
+        assert!(Predicate::parse_search("bogus=X", &schema()).is_err());
+    }
+
+    #[test]
+    fn parse_where_rejects_unknown_operator() {
+        assert!(Predicate::parse_where("state", "~=", "NC", &schema()).is_err());
+    }
+
+    #[test]
+    fn matches_equality_and_ordering_operators() {
+        let schema = schema();
+        let row = vec!["Doe".to_string(), "NC".to_string(), "27601".to_string()];
+
+        let eq = Predicate::parse_where("state", "==", "NC", &schema).unwrap();
+        assert!(eq.matches(&row));
+
+        let ne = Predicate::parse_where("state", "!=", "NC", &schema).unwrap();
+        assert!(!ne.matches(&row));
+
+        let lt = Predicate::parse_where("zip", "<", "30000", &schema).unwrap();
+        assert!(lt.matches(&row));
+
+        let starts_with = Predicate::parse_where("last_name", "starts_with", "Do", &schema).unwrap();
+        assert!(starts_with.matches(&row));
+
+        let ends_with = Predicate::parse_where("last_name", "ends_with", "oe", &schema).unwrap();
+        assert!(ends_with.matches(&row));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::io;
+
+/// A single column in a record layout: a named field of `width` characters
+/// immediately followed by `filler` characters of blank padding.
+#[derive(Debug, Clone)]
+pub struct FieldSpec {
+    pub name: String,
+    pub width: usize,
+    pub filler: usize,
+}
+
+/// A record layout: the ordered fields a CSV row must contain and the
+/// fixed-width output column each maps to. Loaded from a descriptor file so
+/// the tool is not tied to a single COBOL copybook.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    pub fields: Vec<FieldSpec>,
+}
+
+impl Schema {
+    /// The layout matching the original COBOL record:
+    ///   OUT-LAST-NAME  PIC X(25)  + FILLER PIC X(5)
+    ///   OUT-FIRST-NAME PIC X(15)  + FILLER PIC X(5)
+    ///   OUT-STREET     PIC X(30)  + FILLER PIC X(5)
+    ///   OUT-CITY       PIC X(15)  + FILLER PIC X(5)
+    ///   OUT-STATE      PIC XXX    + FILLER PIC X(5)
+    ///   OUT-ZIP        PIC X(10)  + FILLER PIC X(38)
+    pub fn default_copybook() -> Schema {
+        let raw = [
+            ("last_name", 25, 5),
+            ("first_name", 15, 5),
+            ("street", 30, 5),
+            ("city", 15, 5),
+            ("state", 3, 5),
+            ("zip", 10, 38),
+        ];
+        Schema {
+            fields: raw
+                .iter()
+                .map(|&(name, width, filler)| FieldSpec {
+                    name: name.to_string(),
+                    width,
+                    filler,
+                })
+                .collect(),
+        }
+    }
+
+    /// Parse a schema descriptor: one field per line, `name,width,filler`.
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn parse(text: &str) -> Result<Schema, String> {
+        let mut fields = Vec::new();
+        for (line_num, raw_line) in text.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split(',').map(str::trim).collect();
+            if parts.len() != 3 {
+                return Err(format!(
+                    "schema line {}: expected `name,width,filler`, got {:?}",
+                    line_num + 1,
+                    raw_line
+                ));
+            }
+            let width = parts[1].parse::<usize>().map_err(|_| {
+                format!("schema line {}: invalid width {:?}", line_num + 1, parts[1])
+            })?;
+            let filler = parts[2].parse::<usize>().map_err(|_| {
+                format!("schema line {}: invalid filler {:?}", line_num + 1, parts[2])
+            })?;
+            fields.push(FieldSpec {
+                name: parts[0].to_string(),
+                width,
+                filler,
+            });
+        }
+        if fields.is_empty() {
+            return Err("schema has no fields".to_string());
+        }
+        Ok(Schema { fields })
+    }
+
+    /// Load a schema descriptor from disk.
+    pub fn load(path: &str) -> io::Result<Schema> {
+        let text = fs::read_to_string(path)?;
+        Schema::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Find the column index of a field by name, for predicates that refer
+    /// to columns by name on the command line.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fields_in_order() {
+        let schema = Schema::parse("last_name,25,5\nfirst_name,15,5\n").unwrap();
+        assert_eq!(schema.column_count(), 2);
+        assert_eq!(schema.fields[0].name, "last_name");
+        assert_eq!(schema.fields[0].width, 25);
+        assert_eq!(schema.fields[0].filler, 5);
+        assert_eq!(schema.fields[1].name, "first_name");
+    }
+
+    #[test]
+    fn skips_blank_lines_and_comments() {
+        let schema = Schema::parse("# a comment\n\nlast_name,25,5\n  \n").unwrap();
+        assert_eq!(schema.column_count(), 1);
+    }
+
+    #[test]
+    fn rejects_wrong_field_count() {
+        let err = Schema::parse("last_name,25\n").unwrap_err();
+        assert!(err.contains("line 1"));
+    }
+
+    #[test]
+    fn rejects_non_numeric_width() {
+        let err = Schema::parse("last_name,abc,5\n").unwrap_err();
+        assert!(err.contains("invalid width"));
+    }
+
+    #[test]
+    fn rejects_empty_schema() {
+        assert!(Schema::parse("# nothing but comments\n").is_err());
+    }
+
+    #[test]
+    fn index_of_finds_column_by_name() {
+        let schema = Schema::parse("last_name,25,5\nzip,10,38\n").unwrap();
+        assert_eq!(schema.index_of("zip"), Some(1));
+        assert_eq!(schema.index_of("missing"), None);
+    }
+
+    #[test]
+    fn default_copybook_has_six_fields() {
+        assert_eq!(Schema::default_copybook().column_count(), 6);
+    }
+}